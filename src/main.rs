@@ -1,14 +1,20 @@
 use anyhow::Result;
 use chrono::prelude::*;
-use reqwest::blocking::Client;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Stdout, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use std::{collections::HashMap, io::Stdout};
-use std::{io, thread};
 use structopt::StructOpt;
 use termion::raw::{IntoRawMode, RawTerminal};
-use threadpool::ThreadPool;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::Semaphore;
+use tokio::time::interval;
 use tui::widgets::{Block, Borders, Gauge};
 use tui::Terminal;
 use tui::{
@@ -25,7 +31,162 @@ use tui::{
     text::Text,
 };
 
-const MAX_THREADS: usize = 50;
+/// Which wire protocol to run the load test over. `H2` and `H3` both keep a
+/// small pool of persistent, multiplexed connections instead of paying
+/// connection-setup cost per request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    H1,
+    H2,
+    H3,
+}
+
+impl FromStr for Transport {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "h1" => Ok(Transport::H1),
+            "h2" => Ok(Transport::H2),
+            "h3" => Ok(Transport::H3),
+            other => Err(format!("unknown transport '{}', expected h1, h2 or h3", other)),
+        }
+    }
+}
+
+/// Machine-readable export format for `--output`. `Markdown` emits a
+/// GitHub-flavored table suitable for pasting into a PR comment; `Json`
+/// emits a stable schema so multiple runs can be diffed programmatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Json,
+    Markdown,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "markdown" => Ok(OutputFormat::Markdown),
+            other => Err(format!("unknown format '{}', expected json or markdown", other)),
+        }
+    }
+}
+
+/// Log2 of the number of linear sub-buckets per power-of-two magnitude
+/// (`SUB_BUCKET_COUNT`). Raising this trades memory for percentile precision.
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKET_COUNT: usize = 1 << SUB_BUCKET_BITS;
+/// Magnitudes above this cover response times past a year in milliseconds,
+/// far beyond anything `paine` would ever measure.
+const NUM_MAGNITUDES: usize = 48;
+
+/// HdrHistogram-style logarithmic latency histogram: response times are
+/// recorded into `NUM_MAGNITUDES * SUB_BUCKET_COUNT` fixed buckets rather
+/// than an unbounded `Vec<u128>`, so memory stays flat regardless of how
+/// many requests are run. Each power-of-two magnitude is subdivided into
+/// `SUB_BUCKET_COUNT` linear sub-buckets, giving roughly constant relative
+/// precision across the whole range. `min`/`max` are tracked exactly
+/// alongside the buckets since the bucketing itself is lossy.
+#[derive(Debug, Clone)]
+struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+    sum_ms: u128,
+    min_ms: u128,
+    max_ms: u128,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            buckets: vec![0; NUM_MAGNITUDES * SUB_BUCKET_COUNT],
+            count: 0,
+            sum_ms: 0,
+            min_ms: 0,
+            max_ms: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Magnitude `m` (for `v >= 1`) covers the range `[2^m, 2^(m+1) - 1]`;
+    /// `v == 0` is folded into magnitude 0 alongside `v == 1`.
+    fn magnitude(v: u64) -> u32 {
+        if v == 0 {
+            0
+        } else {
+            63 - v.leading_zeros()
+        }
+    }
+
+    /// Returns `(magnitude, sub_bucket)` for a value, bucketing it linearly
+    /// within its magnitude's range.
+    fn bucket_for(v: u64) -> (u32, u32) {
+        let m = Self::magnitude(v).min(NUM_MAGNITUDES as u32 - 1);
+        let (base, width) = if m == 0 { (0, 2) } else { (1u64 << m, 1u64 << m) };
+        let position = v.saturating_sub(base).min(width - 1);
+        let sub = (position * SUB_BUCKET_COUNT as u64) / width;
+        (m, sub as u32)
+    }
+
+    /// The representative (lower-bound) value of a `(magnitude, sub_bucket)`
+    /// pair, used to report an approximate percentile value.
+    fn representative_value(m: u32, sub: u32) -> u128 {
+        let (base, width) = if m == 0 { (0u64, 2u64) } else { (1u64 << m, 1u64 << m) };
+        (base + (sub as u64 * width) / SUB_BUCKET_COUNT as u64) as u128
+    }
+
+    fn flat_index(m: u32, sub: u32) -> usize {
+        m as usize * SUB_BUCKET_COUNT + sub as usize
+    }
+
+    fn record(&mut self, value_ms: u128) {
+        if self.count == 0 {
+            self.min_ms = value_ms;
+            self.max_ms = value_ms;
+        } else {
+            self.min_ms = self.min_ms.min(value_ms);
+            self.max_ms = self.max_ms.max(value_ms);
+        }
+        self.count += 1;
+        self.sum_ms += value_ms;
+
+        let v = value_ms.min(u64::MAX as u128) as u64;
+        let (m, sub) = Self::bucket_for(v);
+        self.buckets[Self::flat_index(m, sub)] += 1;
+    }
+
+    fn avg_ms(&self) -> u128 {
+        if self.count == 0 {
+            0
+        } else {
+            self.sum_ms / self.count as u128
+        }
+    }
+
+    /// Approximate value at quantile `p` (e.g. `0.99` for p99), found by
+    /// scanning buckets in order and accumulating counts until the
+    /// cumulative fraction crosses `p`.
+    fn percentile(&self, p: f64) -> u128 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = ((self.count as f64) * p).ceil() as u64;
+        let mut cumulative = 0u64;
+        for (idx, &count) in self.buckets.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= target {
+                let m = (idx / SUB_BUCKET_COUNT) as u32;
+                let sub = (idx % SUB_BUCKET_COUNT) as u32;
+                return Self::representative_value(m, sub);
+            }
+        }
+        self.max_ms
+    }
+}
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "paine", about = "What about about?")]
@@ -42,8 +203,54 @@ struct TestPlan {
     #[structopt(short, long, default_value = "10", help = "http timeout in seconds")]
     timeout_secs: u64,
 
+    #[structopt(
+        long,
+        default_value = "h1",
+        help = "transport protocol to use: h1, h2 (multiplexed, keep-alive) or h3 (QUIC)"
+    )]
+    transport: Transport,
+
+    #[structopt(
+        long,
+        default_value = "100",
+        help = "max concurrent requests per connection pool slot (requires --transport h2 or h3)"
+    )]
+    max_streams_per_connection: usize,
+
+    #[structopt(long, help = "write the final report to this file")]
+    output: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        default_value = "json",
+        help = "format for --output: json or markdown"
+    )]
+    format: OutputFormat,
+
+    #[structopt(long, default_value = "GET", help = "HTTP method to use")]
+    method: String,
+
+    #[structopt(
+        long = "header",
+        help = "additional request header as 'Key: Value', may be repeated"
+    )]
+    headers: Vec<String>,
+
+    #[structopt(long, help = "request body as a literal string")]
+    body: Option<String>,
+
+    #[structopt(
+        long = "body-file",
+        parse(from_os_str),
+        help = "path to a file to send as the request body"
+    )]
+    body_file: Option<PathBuf>,
+
+    #[structopt(long, help = "Content-Type header for the request body")]
+    content_type: Option<String>,
+
     #[structopt(skip)]
-    response_times: Vec<u128>,
+    latencies: LatencyHistogram,
     #[structopt(skip)]
     status_codes: HashMap<u16, usize>,
     #[structopt(skip)]
@@ -53,36 +260,106 @@ struct TestPlan {
     #[structopt(skip)]
     other_errors: usize,
     #[structopt(skip)]
+    quic_handshake_errors: usize,
+    #[structopt(skip)]
     total_elapsed: Duration,
     #[structopt(skip)]
     total_requests: usize,
     #[structopt(skip)]
     date: String,
+    #[structopt(skip)]
+    connection_streams: HashMap<usize, usize>,
 }
 
 impl TestPlan {
     fn total_errors(&self) -> usize {
-        self.connect_errors + self.timeout_errors + self.other_errors
+        self.connect_errors + self.timeout_errors + self.other_errors + self.quic_handshake_errors
     }
 
     fn response_avg_min_max(&self) -> (u128, u128, u128) {
-        let avg = if self.response_times.is_empty() {
-            0
-        } else {
-            self.response_times.iter().sum::<u128>() / self.response_times.len() as u128
-        };
-
-        let min = *self.response_times.iter().min().unwrap_or(&0);
-        let max = *self.response_times.iter().max().unwrap_or(&0);
+        (self.latencies.avg_ms(), self.latencies.min_ms, self.latencies.max_ms)
+    }
 
-        (avg, min, max)
+    /// p50/p90/p99/p99.9 response times in milliseconds, approximated from
+    /// the latency histogram.
+    fn response_percentiles(&self) -> (u128, u128, u128, u128) {
+        (
+            self.latencies.percentile(0.5),
+            self.latencies.percentile(0.9),
+            self.latencies.percentile(0.99),
+            self.latencies.percentile(0.999),
+        )
     }
 
     fn total_requests(&self) -> usize {
         self.total_requests
     }
+
     fn total_success(&self) -> usize {
-        self.response_times.len()
+        self.latencies.count as usize
+    }
+
+    /// Successful requests per second of wall-clock test time. Shared by
+    /// `draw_terminal` and the `--output` exporters so both report the same
+    /// number. 0.0 while `total_elapsed` is still zero (e.g. a report built
+    /// before the first TUI tick), rather than dividing by zero into `inf`.
+    fn throughput(&self) -> f64 {
+        let elapsed = self.total_elapsed.as_secs_f64();
+        if elapsed == 0.0 {
+            0.0
+        } else {
+            self.total_success() as f64 / elapsed
+        }
+    }
+
+    /// Percentage of requests that succeeded, 0.0 when no requests have
+    /// completed yet.
+    fn success_pct(&self) -> f64 {
+        if self.total_requests() == 0 {
+            0.0
+        } else {
+            self.total_success() as f64 / self.total_requests() as f64 * 100.0
+        }
+    }
+
+    /// Percentage of requests that errored, 0.0 when no requests have
+    /// completed yet.
+    fn error_pct(&self) -> f64 {
+        if self.total_requests() == 0 {
+            0.0
+        } else {
+            self.total_errors() as f64 / self.total_requests() as f64 * 100.0
+        }
+    }
+
+    /// Builds the machine-readable snapshot written by `--output`.
+    fn report(&self) -> Report {
+        let (avg_ms, min_ms, max_ms) = self.response_avg_min_max();
+        let (p50_ms, p90_ms, p99_ms, p999_ms) = self.response_percentiles();
+        Report {
+            date: self.date.clone(),
+            target: self.url.clone(),
+            rate: self.rate,
+            runtime_secs: self.total_elapsed.as_secs_f64(),
+            total_requests: self.total_requests(),
+            total_success: self.total_success(),
+            total_errors: self.total_errors(),
+            success_pct: self.success_pct(),
+            error_pct: self.error_pct(),
+            connect_errors: self.connect_errors,
+            timeout_errors: self.timeout_errors,
+            quic_handshake_errors: self.quic_handshake_errors,
+            other_errors: self.other_errors,
+            status_codes: self.status_codes.clone(),
+            response_avg_ms: avg_ms,
+            response_min_ms: min_ms,
+            response_max_ms: max_ms,
+            response_p50_ms: p50_ms,
+            response_p90_ms: p90_ms,
+            response_p99_ms: p99_ms,
+            response_p999_ms: p999_ms,
+            throughput_req_per_sec: self.throughput(),
+        }
     }
 
     fn draw_terminal(
@@ -97,7 +374,7 @@ impl TestPlan {
                 .constraints(
                     [
                         Constraint::Length(3),
-                        Constraint::Length(12),
+                        Constraint::Length(if self.transport == Transport::H1 { 13 } else { 14 }),
                         Constraint::Min(0),
                     ]
                     .as_ref(),
@@ -126,29 +403,33 @@ impl TestPlan {
             let (avg, min, max) = self.response_avg_min_max();
             let avg_min_max = format!("Avg: {}ms  Min: {}ms  Max: {}ms", avg, min, max);
 
+            let (p50, p90, p99, p999) = self.response_percentiles();
+            let percentiles = format!(
+                "p50: {}ms  p90: {}ms  p99: {}ms  p99.9: {}ms",
+                p50, p90, p99, p999
+            );
+
             let errors = if self.total_errors() > 0 {
                 format!(
-                    "{:.1}% ({}/{}) (Connection: {}  Timeouts: {}  Others: {})",
-                    self.total_errors() / self.total_requests() * 100,
+                    "{:.1}% ({}/{}) (Connection: {}  Timeouts: {}  QUIC handshake: {}  Others: {})",
+                    self.error_pct(),
                     self.total_errors(),
                     self.total_requests(),
                     self.connect_errors,
                     self.timeout_errors,
+                    self.quic_handshake_errors,
                     self.other_errors,
                 )
             } else {
                 "0".to_string()
             };
 
-            let throughput = format!(
-                "{:.1} req/s",
-                (self.total_success() as f64) / (self.total_elapsed.as_secs_f64() as f64)
-            );
+            let throughput = format!("{:.1} req/s", self.throughput());
             let runtime = format!("{:.1}s", self.total_elapsed.as_secs_f64());
             let success = if self.total_success() > 0 {
                 format!(
                     "{:.1}% ({}/{})",
-                    self.total_success() / self.total_requests() * 100,
+                    self.success_pct(),
                     self.total_success(),
                     self.total_requests(),
                 )
@@ -172,8 +453,27 @@ impl TestPlan {
                 Style::default()
             };
 
+            // `connection_streams` counts, per pool slot, the requests that got
+            // a response on it (failed requests are not counted), so the slot
+            // count shown is the number of slots that answered at least once.
+            // The pool size itself is just `--rate`/`--max-streams-per-
+            // connection` restated, and reqwest gives no visibility into how
+            // many real transport connections a slot's client opened
+            // underneath — so this reports the observed per-slot request
+            // distribution rather than claiming a "reuse ratio".
+            let connections = format!(
+                "{} pool slots, requests per slot: {:?}",
+                self.connection_streams.len(),
+                self.connection_streams.values().collect::<Vec<_>>()
+            );
+            let connections_label = match self.transport {
+                Transport::H1 => "Connections",
+                Transport::H2 => "HTTP/2 pool slots",
+                Transport::H3 => "HTTP/3 pool slots",
+            };
+
             let bold_style = Style::default().add_modifier(Modifier::BOLD);
-            let table = Table::new(vec![
+            let mut rows = vec![
                 Row::new(vec!["Date", &self.date]),
                 Row::new(vec![
                     Text::styled("Target", bold_style),
@@ -192,102 +492,561 @@ impl TestPlan {
                 ]),
                 Row::new(vec!["Status codes", status_codes.trim_start_matches(", ")]),
                 Row::new(vec!["Response times", &avg_min_max]),
+                Row::new(vec!["Percentiles", &percentiles]),
                 Row::new(vec![
                     Text::styled("Throughput", througput_style),
                     Text::styled(throughput, througput_style),
                 ]),
-            ])
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Plain)
-                    .title("Test Report"),
-            )
-            .widths(&[Constraint::Length(20), Constraint::Length(70)]);
+            ];
+            if self.transport != Transport::H1 {
+                rows.push(Row::new(vec![connections_label, &connections]));
+            }
+            let table = Table::new(rows)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Plain)
+                        .title("Test Report"),
+                )
+                .widths(&[Constraint::Length(20), Constraint::Length(70)]);
             f.render_widget(table, chunks[1]);
         })?;
         Ok(())
     }
 }
 
+/// Machine-readable snapshot of a completed `TestPlan` run, written to
+/// `--output` in the format chosen by `--format`. Field names and types are
+/// kept stable across releases so multiple runs can be compared
+/// programmatically.
+#[derive(Debug, Serialize)]
+struct Report {
+    date: String,
+    target: String,
+    rate: u16,
+    runtime_secs: f64,
+    total_requests: usize,
+    total_success: usize,
+    total_errors: usize,
+    success_pct: f64,
+    error_pct: f64,
+    connect_errors: usize,
+    timeout_errors: usize,
+    quic_handshake_errors: usize,
+    other_errors: usize,
+    status_codes: HashMap<u16, usize>,
+    response_avg_ms: u128,
+    response_min_ms: u128,
+    response_max_ms: u128,
+    response_p50_ms: u128,
+    response_p90_ms: u128,
+    response_p99_ms: u128,
+    response_p999_ms: u128,
+    throughput_req_per_sec: f64,
+}
+
+impl Report {
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Renders as a GitHub-flavored Markdown table, suitable for pasting
+    /// into a PR comment.
+    fn to_markdown(&self) -> String {
+        let mut status_codes: Vec<_> = self.status_codes.iter().collect();
+        status_codes.sort_by_key(|(code, _)| **code);
+        let status_codes = status_codes
+            .iter()
+            .map(|(code, cnt)| format!("{}: {}", code, cnt))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut md = String::new();
+        md.push_str("| Metric | Value |\n");
+        md.push_str("| --- | --- |\n");
+        md.push_str(&format!("| Date | {} |\n", self.date));
+        md.push_str(&format!("| Target | {} |\n", self.target));
+        md.push_str(&format!("| Rate | {} |\n", self.rate));
+        md.push_str(&format!("| Runtime | {:.1}s |\n", self.runtime_secs));
+        md.push_str(&format!("| Requests | {} |\n", self.total_requests));
+        md.push_str(&format!(
+            "| Success | {:.1}% ({}/{}) |\n",
+            self.success_pct, self.total_success, self.total_requests
+        ));
+        md.push_str(&format!(
+            "| Errors | {:.1}% ({}/{}) (Connection: {}  Timeouts: {}  QUIC handshake: {}  Others: {}) |\n",
+            self.error_pct,
+            self.total_errors,
+            self.total_requests,
+            self.connect_errors,
+            self.timeout_errors,
+            self.quic_handshake_errors,
+            self.other_errors,
+        ));
+        md.push_str(&format!("| Status codes | {} |\n", status_codes));
+        md.push_str(&format!(
+            "| Response times | Avg: {}ms  Min: {}ms  Max: {}ms |\n",
+            self.response_avg_ms, self.response_min_ms, self.response_max_ms
+        ));
+        md.push_str(&format!(
+            "| Percentiles | p50: {}ms  p90: {}ms  p99: {}ms  p99.9: {}ms |\n",
+            self.response_p50_ms, self.response_p90_ms, self.response_p99_ms, self.response_p999_ms
+        ));
+        md.push_str(&format!("| Throughput | {:.1} req/s |\n", self.throughput_req_per_sec));
+        md
+    }
+}
+
 enum Response {
     Error(u16),
     Success(u128, u16),
     TimeoutError,
     ConnectionError,
     OtherError,
+    /// The QUIC handshake itself failed, distinct from a generic
+    /// `ConnectionError` since QUIC separates transport-level handshake
+    /// failures from stream-level errors.
+    QuicHandshakeError,
+    /// A request got a response over pool slot `id`, for the per-slot
+    /// request counts shown in the report for `--transport h2`/`h3`.
+    StreamOnConnection(usize),
+}
+
+/// The method, headers and body every request in a run is built from,
+/// assembled once from the CLI flags in `main` and cloned into each spawned
+/// request task (the way `url` already is) rather than rebuilt per request.
+#[derive(Debug, Clone)]
+struct RequestSpec {
+    method: reqwest::Method,
+    headers: reqwest::header::HeaderMap,
+    body: Option<bytes::Bytes>,
+}
+
+impl RequestSpec {
+    fn from_plan(plan: &TestPlan) -> Result<Self> {
+        let method = plan.method.to_uppercase().parse::<reqwest::Method>()?;
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        for header in &plan.headers {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("invalid --header '{}', expected 'Key: Value'", header))?;
+            headers.append(
+                reqwest::header::HeaderName::from_bytes(name.trim().as_bytes())?,
+                reqwest::header::HeaderValue::from_str(value.trim())?,
+            );
+        }
+
+        let body = match (&plan.body, &plan.body_file) {
+            (Some(_), Some(_)) => anyhow::bail!("--body and --body-file are mutually exclusive"),
+            (Some(body), None) => Some(bytes::Bytes::from(body.clone().into_bytes())),
+            (None, Some(path)) => Some(bytes::Bytes::from(std::fs::read(path)?)),
+            (None, None) => None,
+        };
+
+        if let Some(content_type) = &plan.content_type {
+            headers.insert(
+                reqwest::header::CONTENT_TYPE,
+                reqwest::header::HeaderValue::from_str(content_type)?,
+            );
+        }
+
+        Ok(RequestSpec { method, headers, body })
+    }
+}
+
+/// One pool slot in an `Http2Pool`: a persistent `reqwest::Client`, plus a
+/// `semaphore` that caps how many requests are handed to it concurrently.
+/// `reqwest` doesn't expose its internal connection pool, so this bounds
+/// concurrent requests per slot, not streams multiplexed over one real
+/// transport connection — a slot may still open more than one underneath.
+struct Connection {
+    client: Client,
+    semaphore: Arc<Semaphore>,
+}
+
+/// A small pool of persistent HTTP/2 clients ("slots") that requests are
+/// handed out to round-robin, each capped to `max_streams_per_connection`
+/// concurrent in-flight requests. This is what lets `--transport h2`
+/// amortize connection-setup cost across many requests — see `Connection`
+/// for why the cap is per-slot, not per-connection.
+struct Http2Pool {
+    connections: Vec<Connection>,
+    next: AtomicUsize,
+}
+
+impl Http2Pool {
+    fn new(timeout: Duration, size: usize, max_streams_per_connection: usize) -> Self {
+        let connections = (0..size)
+            .map(|_| Connection {
+                client: reqwest::Client::builder()
+                    .timeout(timeout)
+                    .http2_prior_knowledge()
+                    .build()
+                    .expect("unable to create http/2 client"),
+                semaphore: Arc::new(Semaphore::new(max_streams_per_connection)),
+            })
+            .collect();
+        Http2Pool {
+            connections,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Picks the next connection round-robin, returning its id alongside it.
+    fn pick(&self) -> (usize, &Connection) {
+        let id = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+        (id, &self.connections[id])
+    }
 }
 
-fn do_requests(
+// Spawns one lightweight tokio task per request instead of handing it to a
+// bounded thread pool, so concurrency scales with `rate` rather than being
+// capped at a fixed worker count. Pacing is driven by a `tokio::time::interval`
+// tick per request rather than a blocking `thread::sleep`.
+async fn do_requests(
     client: Client,
     url: &str,
+    spec: &RequestSpec,
     rate: u16,
     total_duration: Duration,
     tx: Sender<Response>,
 ) {
-    let sleep_ms = Duration::from_secs_f64(1.0 / rate as f64);
-    let pool = ThreadPool::new(std::cmp::min(rate as usize, MAX_THREADS));
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / rate as f64));
 
     let started = Instant::now();
     while Instant::now().duration_since(started) < total_duration {
+        ticker.tick().await;
+
         let url = url.to_owned();
+        let spec = spec.clone();
         let tx = tx.clone();
         let client = client.clone();
-        pool.execute(move || {
+        tokio::spawn(async move {
             let req_started = Instant::now();
-            match client.get(url).send() {
+            let mut request = client.request(spec.method.clone(), &url).headers(spec.headers.clone());
+            if let Some(body) = spec.body {
+                request = request.body(body);
+            }
+            match request.send().await {
                 Ok(response) => {
                     if response.status().is_success() {
                         tx.send(Response::Success(
                             req_started.elapsed().as_millis(),
                             response.status().as_u16(),
                         ))
+                        .await
                         .expect("send0 failed");
                     } else {
                         tx.send(Response::Error(response.status().into()))
+                            .await
                             .expect("send0 failed");
                     }
                 }
                 Err(e) => {
                     if e.is_timeout() {
-                        tx.send(Response::TimeoutError).expect("send0 failed");
+                        tx.send(Response::TimeoutError).await.expect("send0 failed");
                     } else if e.is_connect() {
-                        tx.send(Response::ConnectionError).expect("send0 failed");
+                        tx.send(Response::ConnectionError).await.expect("send0 failed");
                     } else {
-                        tx.send(Response::OtherError).expect("send0 failed");
+                        tx.send(Response::OtherError).await.expect("send0 failed");
+                    }
+                }
+            }
+        });
+    }
+    drop(tx);
+}
+
+/// Same pacing/spawn shape as `do_requests`, but requests are handed out
+/// round-robin across the pool's persistent HTTP/2 client slots and each
+/// spawned task first acquires a request permit on its assigned slot, so no
+/// slot ever has more than `max_streams_per_connection` concurrent in-flight
+/// requests (not necessarily concurrent streams on one real connection — see
+/// `Connection`).
+async fn do_requests_http2(
+    pool: Arc<Http2Pool>,
+    url: &str,
+    spec: &RequestSpec,
+    rate: u16,
+    total_duration: Duration,
+    tx: Sender<Response>,
+) {
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / rate as f64));
+
+    let started = Instant::now();
+    while Instant::now().duration_since(started) < total_duration {
+        ticker.tick().await;
+
+        let url = url.to_owned();
+        let spec = spec.clone();
+        let tx = tx.clone();
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let (conn_id, conn) = pool.pick();
+            let semaphore = conn.semaphore.clone();
+            let client = conn.client.clone();
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+
+            let req_started = Instant::now();
+            let mut request = client.request(spec.method.clone(), &url).headers(spec.headers.clone());
+            if let Some(body) = spec.body {
+                request = request.body(body);
+            }
+            match request.send().await {
+                Ok(response) => {
+                    tx.send(Response::StreamOnConnection(conn_id))
+                        .await
+                        .expect("send0 failed");
+                    if response.status().is_success() {
+                        tx.send(Response::Success(
+                            req_started.elapsed().as_millis(),
+                            response.status().as_u16(),
+                        ))
+                        .await
+                        .expect("send0 failed");
+                    } else {
+                        tx.send(Response::Error(response.status().into()))
+                            .await
+                            .expect("send0 failed");
+                    }
+                }
+                Err(e) => {
+                    if e.is_timeout() {
+                        tx.send(Response::TimeoutError).await.expect("send0 failed");
+                    } else if e.is_connect() {
+                        tx.send(Response::ConnectionError).await.expect("send0 failed");
+                    } else {
+                        tx.send(Response::OtherError).await.expect("send0 failed");
+                    }
+                }
+            }
+        });
+    }
+    drop(tx);
+}
+
+/// One persistent HTTP/3 (QUIC) connection in an `Http3Pool`, mirroring
+/// `Connection` for the HTTP/2 pool. `send_request` is cheap to clone, so
+/// each spawned task clones it rather than sharing a reference.
+struct Http3Connection {
+    send_request: h3::client::SendRequest<h3_quinn::OpenStreams, bytes::Bytes>,
+    semaphore: Arc<Semaphore>,
+}
+
+/// A small pool of persistent QUIC connections, each driving an HTTP/3
+/// connection that requests are multiplexed over, capped to
+/// `max_streams_per_connection` concurrent streams. Connections that fail
+/// their QUIC handshake are dropped from the pool and counted separately
+/// so they show up as `quic_handshake_errors` rather than generic
+/// connection errors.
+///
+/// Unlike `Http2Pool`, this isn't shared behind an `Arc`: the boxed stream
+/// inside `Http3Connection::send_request` is `Send` but not `Sync`, so the
+/// pool is kept and round-robined by the single task that drives
+/// `do_requests_http3`, and each spawned request task only receives an
+/// owned clone of the connection it was assigned.
+struct Http3Pool {
+    connections: Vec<Http3Connection>,
+}
+
+impl Http3Pool {
+    async fn new(
+        host: &str,
+        addr: SocketAddr,
+        size: usize,
+        max_streams_per_connection: usize,
+    ) -> Result<(Self, usize)> {
+        let mut tls_config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(load_native_roots())
+            .with_no_client_auth();
+        tls_config.alpn_protocols = vec![b"h3".to_vec()];
+        let client_config = quinn::ClientConfig::new(Arc::new(tls_config));
+
+        let mut endpoint = quinn::Endpoint::client("[::]:0".parse().unwrap())?;
+        endpoint.set_default_client_config(client_config);
+
+        let mut connections = Vec::with_capacity(size);
+        let mut handshake_errors = 0;
+        for _ in 0..size {
+            match connect_http3(&endpoint, addr, host, max_streams_per_connection).await {
+                Ok(connection) => connections.push(connection),
+                Err(_) => handshake_errors += 1,
+            }
+        }
+
+        if connections.is_empty() {
+            anyhow::bail!("all {} QUIC handshakes to {} failed", size, addr);
+        }
+
+        Ok((Http3Pool { connections }, handshake_errors))
+    }
+}
+
+fn load_native_roots() -> rustls::RootCertStore {
+    let mut roots = rustls::RootCertStore::empty();
+    if let Ok(certs) = rustls_native_certs::load_native_certs() {
+        for cert in certs {
+            let _ = roots.add(&rustls::Certificate(cert.0));
+        }
+    }
+    roots
+}
+
+async fn connect_http3(
+    endpoint: &quinn::Endpoint,
+    addr: SocketAddr,
+    host: &str,
+    max_streams_per_connection: usize,
+) -> Result<Http3Connection> {
+    let quic_conn = endpoint.connect(addr, host)?.await?;
+    let h3_conn = h3_quinn::Connection::new(quic_conn);
+    let (mut driver, send_request) = h3::client::new(h3_conn).await?;
+    tokio::spawn(async move {
+        futures::future::poll_fn(|cx| driver.poll_close(cx)).await.ok();
+    });
+
+    Ok(Http3Connection {
+        send_request,
+        semaphore: Arc::new(Semaphore::new(max_streams_per_connection)),
+    })
+}
+
+/// Same pacing/spawn/round-robin shape as `do_requests_http2`, but issues
+/// requests as HTTP/3 streams multiplexed over the pool's QUIC connections.
+/// The pool itself stays local to this task; each spawned request task gets
+/// only an owned clone of the connection it was assigned.
+async fn do_requests_http3(
+    pool: Http3Pool,
+    url: &str,
+    spec: &RequestSpec,
+    rate: u16,
+    total_duration: Duration,
+    tx: Sender<Response>,
+) {
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / rate as f64));
+    let mut next_conn = 0usize;
+
+    let started = Instant::now();
+    while Instant::now().duration_since(started) < total_duration {
+        ticker.tick().await;
+
+        let conn_id = next_conn % pool.connections.len();
+        next_conn = next_conn.wrapping_add(1);
+        let semaphore = pool.connections[conn_id].semaphore.clone();
+        let send_request = pool.connections[conn_id].send_request.clone();
+
+        let url = url.to_owned();
+        let spec = spec.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut send_request = send_request;
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+
+            let req_started = Instant::now();
+            let mut builder = http::Request::builder().method(spec.method.clone()).uri(&url);
+            for (name, value) in spec.headers.iter() {
+                builder = builder.header(name, value);
+            }
+            let request = builder.body(()).expect("invalid request url");
+            match send_request.send_request(request).await {
+                Ok(mut stream) => {
+                    if let Some(body) = spec.body {
+                        stream.send_data(body).await.ok();
+                    }
+                    stream.finish().await.ok();
+                    match stream.recv_response().await {
+                        Ok(response) => {
+                            tx.send(Response::StreamOnConnection(conn_id))
+                                .await
+                                .expect("send0 failed");
+                            if response.status().is_success() {
+                                tx.send(Response::Success(
+                                    req_started.elapsed().as_millis(),
+                                    response.status().as_u16(),
+                                ))
+                                .await
+                                .expect("send0 failed");
+                            } else {
+                                tx.send(Response::Error(response.status().as_u16()))
+                                    .await
+                                    .expect("send0 failed");
+                            }
+                        }
+                        Err(e) => tx.send(classify_h3_error(&e)).await.expect("send0 failed"),
                     }
                 }
+                Err(e) => tx.send(classify_h3_error(&e)).await.expect("send0 failed"),
             }
         });
-        std::thread::sleep(sleep_ms);
     }
     drop(tx);
 }
 
-fn handle_results(data: Arc<Mutex<TestPlan>>, rx: Receiver<Response>) {
-    for msg in rx.iter() {
+/// Maps an `h3::Error` from `send_request`/`recv_response` onto the same
+/// `Response` error variants `do_requests`/`do_requests_http2` derive from
+/// `reqwest::Error`, so h3 failures get the same timeout/connection/other
+/// classification instead of collapsing into `OtherError`.
+fn classify_h3_error(err: &h3::Error) -> Response {
+    match err.kind() {
+        h3::error::Kind::Timeout => Response::TimeoutError,
+        h3::error::Kind::Closed | h3::error::Kind::Closing => Response::ConnectionError,
+        h3::error::Kind::Application { .. } | h3::error::Kind::HeaderTooBig { .. } => Response::OtherError,
+        // `Kind::Transport` wraps a QUIC transport error type that isn't
+        // nameable outside the `h3`/`quinn` crates, and `Kind` itself is
+        // `#[non_exhaustive]`, so this wildcard is the only way to reach it;
+        // it's where real connection resets/drops surface.
+        _ => Response::ConnectionError,
+    }
+}
+
+async fn handle_results(data: Arc<Mutex<TestPlan>>, mut rx: Receiver<Response>) {
+    while let Some(msg) = rx.recv().await {
         let mut plan = data.lock().unwrap();
-        plan.total_requests += 1;
 
         match msg {
+            Response::StreamOnConnection(conn_id) => {
+                let entry = plan.connection_streams.entry(conn_id).or_insert(0);
+                *entry += 1;
+            }
             Response::Success(millis, code) => {
-                plan.response_times.push(millis);
+                plan.total_requests += 1;
+                plan.latencies.record(millis);
                 let entry = plan.status_codes.entry(code).or_insert(0);
                 *entry += 1;
             }
             Response::Error(code) => {
+                plan.total_requests += 1;
                 plan.other_errors += 1;
                 let entry = plan.status_codes.entry(code).or_insert(0);
                 *entry += 1;
             }
-            Response::OtherError => plan.other_errors = plan.other_errors + 1,
-            Response::ConnectionError => plan.connect_errors = plan.connect_errors + 1,
-            Response::TimeoutError => plan.timeout_errors = plan.timeout_errors + 1,
+            Response::OtherError => {
+                plan.total_requests += 1;
+                plan.other_errors += 1;
+            }
+            Response::ConnectionError => {
+                plan.total_requests += 1;
+                plan.connect_errors += 1;
+            }
+            Response::TimeoutError => {
+                plan.total_requests += 1;
+                plan.timeout_errors += 1;
+            }
+            Response::QuicHandshakeError => {
+                // A failed handshake never issued a request, so it's counted
+                // towards `quic_handshake_errors`/`total_errors()` but kept
+                // out of `total_requests` — otherwise it would inflate the
+                // denominator `success_pct`/`error_pct` divide by.
+                plan.quic_handshake_errors += 1;
+            }
         }
     }
 }
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     let mut plan = TestPlan::from_args();
     if plan.rate <= 0 {
         anyhow::bail!("<rate> must be greated than 0.");
@@ -295,26 +1054,67 @@ fn main() -> Result<()> {
     if plan.timeout_secs <= 0 {
         anyhow::bail!("<timeout> must be greated than 0.");
     }
+    if plan.max_streams_per_connection == 0 {
+        anyhow::bail!("<max-streams-per-connection> must be greated than 0.");
+    }
     plan.date = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
     let rate = plan.rate;
     let url = plan.url.clone();
     let timeout_secs = plan.timeout_secs;
     let duration = Duration::from_secs(plan.duration as u64);
+    let transport = plan.transport;
+    let max_streams_per_connection = plan.max_streams_per_connection;
+    let spec = RequestSpec::from_plan(&plan)?;
 
     // run response handler
     let data = Arc::new(Mutex::new(plan));
     let data2 = data.clone();
-    let (tx, rx) = channel();
-    let handle_results = thread::spawn(move || handle_results(data2, rx));
+    let (tx, rx) = mpsc::channel(1024);
+    let handle_results = tokio::spawn(handle_results(data2, rx));
 
     // run request executor
     let tx2 = tx.clone();
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs))
-        .build()
-        .expect("unable to create http client");
-    let handle_do = thread::spawn(move || do_requests(client, &url, rate, duration, tx2));
+    let timeout = Duration::from_secs(timeout_secs);
+    let num_connections = ((rate as usize).max(1) + max_streams_per_connection - 1)
+        / max_streams_per_connection.max(1);
+    let handle_do = match transport {
+        Transport::H1 => {
+            let client = reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("unable to create http client");
+            tokio::spawn(async move { do_requests(client, &url, &spec, rate, duration, tx2).await })
+        }
+        Transport::H2 => {
+            let pool = Arc::new(Http2Pool::new(
+                timeout,
+                num_connections.max(1),
+                max_streams_per_connection,
+            ));
+            tokio::spawn(async move { do_requests_http2(pool, &url, &spec, rate, duration, tx2).await })
+        }
+        Transport::H3 => {
+            let parsed = url::Url::parse(&url)?;
+            let host = parsed
+                .host_str()
+                .ok_or_else(|| anyhow::anyhow!("url '{}' has no host", url))?
+                .to_owned();
+            let port = parsed.port_or_known_default().unwrap_or(443);
+            let addr = tokio::net::lookup_host((host.as_str(), port))
+                .await?
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("could not resolve host '{}'", host))?;
+            let (pool, handshake_errors) =
+                Http3Pool::new(&host, addr, num_connections.max(1), max_streams_per_connection)
+                    .await?;
+            for _ in 0..handshake_errors {
+                tx2.send(Response::QuicHandshakeError).await.expect("send0 failed");
+            }
+            tokio::spawn(async move { do_requests_http3(pool, &url, &spec, rate, duration, tx2).await })
+        }
+    };
+    drop(tx);
 
     // prepare terminal
     let stdout = io::stdout().into_raw_mode()?;
@@ -330,24 +1130,151 @@ fn main() -> Result<()> {
         if ratio >= 1.0 {
             ratio = 1.0;
         }
-        let mut plan = data.lock().unwrap();
-        plan.total_elapsed = now.duration_since(started);
-        plan.draw_terminal(&mut terminal, &ratio)?;
-        drop(plan);
+        {
+            let mut plan = data.lock().unwrap();
+            plan.total_elapsed = now.duration_since(started);
+            plan.draw_terminal(&mut terminal, &ratio)?;
+        }
 
         if ratio >= 1.0 {
-            drop(tx);
             break;
         }
 
-        thread::sleep(Duration::from_secs_f64(0.2));
+        tokio::time::sleep(Duration::from_secs_f64(0.2)).await;
     }
 
-    handle_do.join().expect("do join failed");
-    handle_results.join().expect("results join failed");
+    handle_do.await.expect("do join failed");
+    handle_results.await.expect("results join failed");
 
     // let _ = stdin().keys().next();
     terminal.set_cursor(0, 16)?;
 
+    let plan = data.lock().unwrap();
+    if let Some(output) = &plan.output {
+        let report = plan.report();
+        let rendered = match plan.format {
+            OutputFormat::Json => report.to_json()?,
+            OutputFormat::Markdown => report.to_markdown(),
+        };
+        std::fs::File::create(output)?.write_all(rendered.as_bytes())?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod latency_histogram_tests {
+    use super::*;
+
+    #[test]
+    fn magnitude_boundaries() {
+        assert_eq!(LatencyHistogram::magnitude(0), 0);
+        assert_eq!(LatencyHistogram::magnitude(1), 0);
+        assert_eq!(LatencyHistogram::magnitude(2), 1);
+        assert_eq!(LatencyHistogram::magnitude(3), 1);
+        assert_eq!(LatencyHistogram::magnitude(4), 2);
+        assert_eq!(LatencyHistogram::magnitude(7), 2);
+        assert_eq!(LatencyHistogram::magnitude(8), 3);
+    }
+
+    #[test]
+    fn bucket_for_sub_bucket_boundaries() {
+        // magnitude 6 covers [64, 127], width 64, 32 sub-buckets of 2ms each.
+        assert_eq!(LatencyHistogram::bucket_for(64), (6, 0));
+        assert_eq!(LatencyHistogram::bucket_for(65), (6, 0));
+        assert_eq!(LatencyHistogram::bucket_for(66), (6, 1));
+        assert_eq!(LatencyHistogram::bucket_for(126), (6, 31));
+        assert_eq!(LatencyHistogram::bucket_for(127), (6, 31));
+    }
+
+    #[test]
+    fn representative_value_is_bucket_lower_bound() {
+        assert_eq!(LatencyHistogram::representative_value(6, 0), 64);
+        assert_eq!(LatencyHistogram::representative_value(6, 1), 66);
+        assert_eq!(LatencyHistogram::representative_value(6, 31), 126);
+        assert_eq!(LatencyHistogram::representative_value(0, 0), 0);
+        assert_eq!(LatencyHistogram::representative_value(0, 16), 1);
+    }
+
+    #[test]
+    fn percentile_on_known_uniform_samples() {
+        // One sample per sub-bucket across magnitude 6: 64, 66, 68, ..., 126.
+        let mut h = LatencyHistogram::default();
+        for v in (64..128).step_by(2) {
+            h.record(v as u128);
+        }
+        assert_eq!(h.count, 32);
+        assert_eq!(h.min_ms, 64);
+        assert_eq!(h.max_ms, 126);
+        assert_eq!(h.avg_ms(), 95);
+        // target = ceil(32*0.5) = 16th sample -> sub-bucket 15 -> 64 + 2*15
+        assert_eq!(h.percentile(0.5), 94);
+        // target = ceil(32*0.9) = 29th sample -> sub-bucket 28 -> 64 + 2*28
+        assert_eq!(h.percentile(0.9), 120);
+        // target = ceil(32*0.99) = 32nd sample -> sub-bucket 31 -> 126
+        assert_eq!(h.percentile(0.99), 126);
+    }
+
+    #[test]
+    fn percentile_on_empty_histogram_is_zero() {
+        let h = LatencyHistogram::default();
+        assert_eq!(h.percentile(0.99), 0);
+        assert_eq!(h.avg_ms(), 0);
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use super::*;
+
+    fn sample_report() -> Report {
+        let mut status_codes = HashMap::new();
+        status_codes.insert(200, 8);
+        status_codes.insert(500, 2);
+        Report {
+            date: "2024-01-01T00:00:00Z".to_string(),
+            target: "http://example.com".to_string(),
+            rate: 10,
+            runtime_secs: 5.0,
+            total_requests: 10,
+            total_success: 8,
+            total_errors: 2,
+            success_pct: 80.0,
+            error_pct: 20.0,
+            connect_errors: 1,
+            timeout_errors: 1,
+            quic_handshake_errors: 0,
+            other_errors: 0,
+            status_codes,
+            response_avg_ms: 50,
+            response_min_ms: 10,
+            response_max_ms: 200,
+            response_p50_ms: 45,
+            response_p90_ms: 150,
+            response_p99_ms: 190,
+            response_p999_ms: 200,
+            throughput_req_per_sec: 2.0,
+        }
+    }
+
+    #[test]
+    fn to_json_has_expected_schema() {
+        let json = sample_report().to_json().expect("serialization should succeed");
+        let value: serde_json::Value = serde_json::from_str(&json).expect("valid json");
+        assert_eq!(value["target"], "http://example.com");
+        assert_eq!(value["total_requests"], 10);
+        assert_eq!(value["response_p99_ms"], 190);
+        assert_eq!(value["status_codes"]["200"], 8);
+    }
+
+    #[test]
+    fn to_markdown_renders_a_gfm_table() {
+        let md = sample_report().to_markdown();
+        let lines: Vec<&str> = md.lines().collect();
+        assert_eq!(lines[0], "| Metric | Value |");
+        assert_eq!(lines[1], "| --- | --- |");
+        assert!(lines.iter().all(|line| line.starts_with('|') && line.ends_with('|')));
+        assert!(md.contains("p50: 45ms"));
+        assert!(md.contains("200: 8, 500: 2"));
+    }
+}